@@ -0,0 +1,332 @@
+//! A [`Recorder`] that streams measurements to a time-series database using
+//! InfluxDB line protocol instead of writing an offline FST waveform.
+//!
+//! The architecture mirrors the FST backend: a worker thread owns the network
+//! transport and drains a [`crossbeam`] channel of [`Command`]s. Updates are
+//! batched by the current timestamp and serialized into line-protocol lines of
+//! the form `measurement[,tag=value...] field=value timestamp`, then flushed to the database
+//! once the outgoing buffer grows past a size threshold or enough wall-clock
+//! time has elapsed.
+
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, UdpSocket},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crossbeam::channel::{unbounded, Receiver, Sender};
+use telemetry_facade::recorder::*;
+
+use crate::{TelemetryError, Value};
+
+/// Flush once the pending line-protocol buffer grows past this many bytes.
+const FLUSH_BYTES: usize = 64 * 1024;
+/// Flush at least this often even when traffic is light.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+struct InfluxRecorderImpl(Sender<Command>);
+
+impl Recorder for InfluxRecorderImpl {
+    fn allocate(&self, name: &str, kind: MetricKind, _unit: &str, labels: &[(&str, &str)]) -> MetricId {
+        let (reply_tx, reply_rx) = crossbeam::channel::bounded(1);
+        let _ = self.0.send(Command::Register {
+            name: name.into(),
+            kind,
+            tags: labels
+                .iter()
+                .map(|(k, v)| format!(",{}={}", escape_tag(k), escape_tag(v)))
+                .collect::<String>(),
+            reply: reply_tx,
+        });
+        reply_rx.recv().unwrap_or(MetricId::MAX)
+    }
+
+    fn record_i64(&self, id: usize, value: i64) {
+        let _ = self.0.send(Command::Update {
+            metric: id,
+            value: Value::Int64(value),
+        });
+    }
+
+    fn record_u64(&self, id: usize, value: u64) {
+        let _ = self.0.send(Command::Update {
+            metric: id,
+            value: Value::Uint64(value),
+        });
+    }
+
+    fn record_f64(&self, id: usize, value: f64) {
+        let _ = self.0.send(Command::Update {
+            metric: id,
+            value: Value::Float64(value),
+        });
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Command {
+    Register {
+        name: String,
+        kind: MetricKind,
+        tags: String,
+        reply: Sender<MetricId>,
+    },
+    Timestamp(u64),
+    Update {
+        metric: MetricId,
+        value: Value,
+    },
+    Exit,
+}
+
+/// Streams telemetry to an InfluxDB-compatible database over HTTP or UDP.
+///
+/// Pass an `http://host:port` URL to POST batches to `/write?db=<database>`,
+/// or a `udp://host:port` URL to fire line protocol packets at a UDP listener.
+/// Constructing it installs the recorder on the facade, so swapping file
+/// capture for live streaming is a one-line change in the user's setup.
+pub struct InfluxRecorder {
+    handle: Option<JoinHandle<Result<(), TelemetryError>>>,
+    cmd_tx: Sender<Command>,
+}
+
+impl InfluxRecorder {
+    pub fn new(url: &str, database: &str) -> Result<Self, TelemetryError> {
+        let sink = Sink::connect(url, database)?;
+
+        let (cmd_tx, cmd_rx) = unbounded();
+        let handle = Some(Worker::spawn(sink, cmd_rx));
+        telemetry_facade::set_recorder(InfluxRecorderImpl(cmd_tx.clone()));
+        Ok(Self { handle, cmd_tx })
+    }
+
+    pub fn timestamp_secs_f64(&self, time: f64) {
+        let ts = time * 1_000_000_000.0;
+        let _ = self.cmd_tx.send(Command::Timestamp(ts as u64));
+    }
+}
+
+impl Drop for InfluxRecorder {
+    fn drop(&mut self) {
+        let _ = self.cmd_tx.send(Command::Exit);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+struct Worker {
+    sink: Sink,
+    cmd_rx: Receiver<Command>,
+    metrics: Vec<Metric>,
+    /// The timestamp the currently accumulating batch belongs to, in ns.
+    current_ts: u64,
+    /// Latest value seen for each metric within the current timestamp.
+    pending: Vec<Option<Value>>,
+    buffer: String,
+    last_flush: Instant,
+}
+
+impl Worker {
+    fn spawn(sink: Sink, cmd_rx: Receiver<Command>) -> JoinHandle<Result<(), TelemetryError>> {
+        std::thread::spawn(move || {
+            let mut worker = Worker {
+                sink,
+                cmd_rx,
+                metrics: vec![],
+                current_ts: 0,
+                pending: vec![],
+                buffer: String::new(),
+                last_flush: Instant::now(),
+            };
+            worker.run()
+        })
+    }
+
+    fn run(&mut self) -> Result<(), TelemetryError> {
+        while let Ok(msg) = self.cmd_rx.recv() {
+            match msg {
+                Command::Register {
+                    name,
+                    kind,
+                    tags,
+                    reply,
+                } => {
+                    let id = self.metrics.len();
+                    self.metrics.push(Metric { name, kind, tags });
+                    self.pending.push(None);
+                    let _ = reply.send(id);
+                }
+                Command::Timestamp(ts) => {
+                    self.serialize_batch();
+                    self.current_ts = ts;
+                    self.maybe_flush(false)?;
+                }
+                Command::Update { metric, value } => {
+                    if let Some(slot) = self.pending.get_mut(metric) {
+                        *slot = Some(value);
+                    }
+                }
+                Command::Exit => break,
+            }
+        }
+        self.serialize_batch();
+        self.maybe_flush(true)?;
+        Ok(())
+    }
+
+    /// Emit a line-protocol line for every metric updated during the current
+    /// timestamp, then clear the batch for the next one.
+    fn serialize_batch(&mut self) {
+        for (metric, slot) in self.metrics.iter().zip(self.pending.iter_mut()) {
+            let Some(value) = slot.take() else {
+                continue;
+            };
+            self.buffer.push_str(&escape_measurement(&metric.name));
+            self.buffer.push_str(&metric.tags);
+            self.buffer.push(' ');
+            self.buffer.push_str(&field(&value));
+            self.buffer.push(' ');
+            self.buffer.push_str(&self.current_ts.to_string());
+            self.buffer.push('\n');
+        }
+    }
+
+    fn maybe_flush(&mut self, force: bool) -> Result<(), TelemetryError> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let due = force
+            || self.buffer.len() >= FLUSH_BYTES
+            || self.last_flush.elapsed() >= FLUSH_INTERVAL;
+        if !due {
+            return Ok(());
+        }
+        self.sink.send(self.buffer.as_bytes())?;
+        self.buffer.clear();
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+}
+
+struct Metric {
+    name: String,
+    #[allow(dead_code)]
+    kind: MetricKind,
+    /// Pre-rendered tag set (`,key=value` per label) for line protocol.
+    tags: String,
+}
+
+/// Escape a measurement name for line protocol: commas and spaces are special
+/// in the measurement position and must be backslash-escaped.
+fn escape_measurement(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for ch in name.chars() {
+        if matches!(ch, ',' | ' ') {
+            out.push('\\');
+        }
+        out.push(ch);
+    }
+    out
+}
+
+/// Escape a tag key or value for line protocol: commas, equals signs and
+/// spaces are the separators between and within tags and must be escaped.
+fn escape_tag(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for ch in value.chars() {
+        if matches!(ch, ',' | '=' | ' ') {
+            out.push('\\');
+        }
+        out.push(ch);
+    }
+    out
+}
+
+/// Render the typed field for a value: integers carry an `i` field keyed `i`,
+/// unsigned a `u` field keyed `u`, and reals a bare `f` field.
+fn field(value: &Value) -> String {
+    match value {
+        Value::Int64(v) => format!("i={v}i"),
+        Value::Uint64(v) => format!("u={v}u"),
+        Value::Float64(v) => format!("f={v}"),
+    }
+}
+
+/// Parse the numeric status code out of an HTTP response's status line
+/// (`HTTP/1.1 204 No Content`), returning `None` if the response is malformed.
+fn http_status(response: &[u8]) -> Option<u16> {
+    let head = response.splitn(2, |&b| b == b'\r').next()?;
+    let line = std::str::from_utf8(head).ok()?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// The outgoing network transport for batched line protocol.
+enum Sink {
+    Udp {
+        socket: UdpSocket,
+        target: String,
+    },
+    Http {
+        host: String,
+        port: u16,
+        path: String,
+    },
+}
+
+impl Sink {
+    fn connect(url: &str, database: &str) -> Result<Self, TelemetryError> {
+        if let Some(rest) = url.strip_prefix("udp://") {
+            let target = rest.trim_end_matches('/').to_string();
+            let socket = UdpSocket::bind("0.0.0.0:0")?;
+            Ok(Sink::Udp { socket, target })
+        } else {
+            let rest = url
+                .strip_prefix("http://")
+                .unwrap_or(url)
+                .trim_end_matches('/');
+            let (host, port) = match rest.split_once(':') {
+                Some((host, port)) => (host.to_string(), port.parse().unwrap_or(8086)),
+                None => (rest.to_string(), 8086),
+            };
+            Ok(Sink::Http {
+                host,
+                port,
+                path: format!("/write?db={database}"),
+            })
+        }
+    }
+
+    fn send(&self, payload: &[u8]) -> Result<(), TelemetryError> {
+        match self {
+            Sink::Udp { socket, target } => {
+                socket.send_to(payload, target)?;
+            }
+            Sink::Http { host, port, path } => {
+                let mut stream = TcpStream::connect((host.as_str(), *port))?;
+                let header = format!(
+                    "POST {path} HTTP/1.1\r\nHost: {host}:{port}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    payload.len()
+                );
+                stream.write_all(header.as_bytes())?;
+                stream.write_all(payload)?;
+                stream.flush()?;
+                // Read the response and surface a non-2xx status so a rejected
+                // batch (e.g. malformed line protocol) does not disappear.
+                let mut response = Vec::new();
+                stream.read_to_end(&mut response)?;
+                if let Some(status) = http_status(&response) {
+                    if !(200..300).contains(&status) {
+                        let body = String::from_utf8_lossy(&response);
+                        let detail = body.rsplit("\r\n\r\n").next().unwrap_or("").trim();
+                        return Err(TelemetryError::InfluxWrite(format!(
+                            "HTTP {status}: {detail}"
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
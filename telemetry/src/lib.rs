@@ -9,15 +9,23 @@ use fstapi::{Handle, Writer};
 pub use telemetry_facade::metric;
 use telemetry_facade::recorder::*;
 
+mod influx;
+pub use influx::InfluxRecorder;
+
 struct TelemetryRecorderImpl(Sender<Command>);
 
 impl Recorder for TelemetryRecorderImpl {
-    fn allocate(&self, name: &str, kind: MetricKind, unit: &str) -> MetricId {
+    fn allocate(&self, name: &str, kind: MetricKind, unit: &str, labels: &[(&str, &str)]) -> MetricId {
         let (reply_tx, reply_rx) = bounded(1);
         let _ = self.0.send(Command::Register {
             name: name.into(),
             kind,
             unit: unit.into(),
+            labels: labels
+                .iter()
+                .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+                .collect(),
+            aggregation: None,
             reply: reply_tx,
         });
         reply_rx.recv().unwrap_or(MetricId::MAX)
@@ -43,14 +51,46 @@ impl Recorder for TelemetryRecorderImpl {
             value: Value::Float64(value),
         });
     }
+
+    fn allocate_aggregate(
+        &self,
+        name: &str,
+        aggregation: Aggregation,
+        value_kind: MetricKind,
+        unit: &str,
+        labels: &[(&str, &str)],
+    ) -> MetricId {
+        let (reply_tx, reply_rx) = bounded(1);
+        let _ = self.0.send(Command::Register {
+            name: name.into(),
+            kind: value_kind,
+            unit: unit.into(),
+            labels: labels
+                .iter()
+                .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+                .collect(),
+            aggregation: Some(aggregation),
+            reply: reply_tx,
+        });
+        reply_rx.recv().unwrap_or(MetricId::MAX)
+    }
+
+    fn increment(&self, id: usize, delta: i64) {
+        let _ = self.0.send(Command::Increment { metric: id, delta });
+    }
+
+    fn observe(&self, id: usize, value: f64) {
+        let _ = self.0.send(Command::Observe { metric: id, value });
+    }
 }
 
-#[derive(Debug, Clone)]
 enum Command {
     Register {
         name: String,
         kind: MetricKind,
         unit: String,
+        labels: Vec<(String, String)>,
+        aggregation: Option<Aggregation>,
         reply: Sender<MetricId>,
     },
     Timestamp(u64),
@@ -58,11 +98,84 @@ enum Command {
         metric: MetricId,
         value: Value,
     },
+    Increment {
+        metric: MetricId,
+        delta: i64,
+    },
+    Observe {
+        metric: MetricId,
+        value: f64,
+    },
+    Query {
+        selector: Selector,
+        reply: Sender<Reply>,
+    },
     Exit,
 }
 
+/// A runtime inspection request answered from the worker's metric table.
+enum Selector {
+    /// List every registered metric with its kind, unit and last value.
+    List,
+    /// The most recent value of the named metric, if it exists.
+    Value(String),
+    /// Install a trip that fires when `predicate` first holds for a metric,
+    /// recording a marker channel and reporting over `events`.
+    Watch {
+        name: String,
+        predicate: Predicate,
+        events: Sender<WatchEvent>,
+    },
+}
+
+/// One-shot answer to a [`Selector`].
+enum Reply {
+    List(Vec<MetricSnapshot>),
+    Value(Option<f64>),
+    /// Whether the watch was accepted — armed now if the metric exists, or
+    /// queued to arm when it first registers.
+    Watch(bool),
+}
+
+type Predicate = Box<dyn Fn(f64) -> bool + Send>;
+
+/// A point-in-time description of a registered metric.
+#[derive(Debug, Clone)]
+pub struct MetricSnapshot {
+    pub name: String,
+    pub kind: MetricKind,
+    pub unit: String,
+    pub value: Option<f64>,
+}
+
+/// Reported when a watched metric crosses its predicate bound.
 #[derive(Debug, Clone)]
-enum Value {
+pub struct WatchEvent {
+    pub name: String,
+    pub value: f64,
+    pub timestamp: u64,
+}
+
+/// An installed threshold trip on a single metric.
+struct Watch {
+    metric: MetricId,
+    predicate: Predicate,
+    marker: Handle,
+    armed: bool,
+    events: Sender<WatchEvent>,
+}
+
+/// A watch requested for a metric that has not been registered yet. Metrics are
+/// allocated lazily on the first `metric!` hit, so a test harness that installs
+/// a watch up front is queued here and bound when the metric first registers.
+struct PendingWatch {
+    name: String,
+    predicate: Predicate,
+    events: Sender<WatchEvent>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Value {
     Int64(i64),
     Uint64(u64),
     Float64(f64),
@@ -72,6 +185,10 @@ enum Value {
 pub enum TelemetryError {
     #[error("fst error: {0}")]
     FstApi(fstapi::Error),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("influx write rejected: {0}")]
+    InfluxWrite(String),
 }
 
 impl From<fstapi::Error> for TelemetryError {
@@ -105,8 +222,66 @@ impl TelemetryRecorder {
         let ts = time * 1_000_000_000.0;
         let _ = self.cmd_tx.send(Command::Timestamp(ts as u64));
     }
+
+    /// List every registered metric with its kind, unit and latest value,
+    /// without having to parse the recorded FST file.
+    pub fn snapshot(&self) -> Vec<MetricSnapshot> {
+        let (reply_tx, reply_rx) = bounded(1);
+        let _ = self.cmd_tx.send(Command::Query {
+            selector: Selector::List,
+            reply: reply_tx,
+        });
+        match reply_rx.recv() {
+            Ok(Reply::List(list)) => list,
+            _ => vec![],
+        }
+    }
+
+    /// Fetch the most recent value of a metric by name, if it exists.
+    pub fn value(&self, name: &str) -> Option<f64> {
+        let (reply_tx, reply_rx) = bounded(1);
+        let _ = self.cmd_tx.send(Command::Query {
+            selector: Selector::Value(name.into()),
+            reply: reply_tx,
+        });
+        match reply_rx.recv() {
+            Ok(Reply::Value(value)) => value,
+            _ => None,
+        }
+    }
+
+    /// Install a threshold trip on a metric. Whenever `predicate` first holds
+    /// for a newly recorded value, a marker channel is recorded and a
+    /// [`WatchEvent`] is delivered over the returned channel; the trip re-arms
+    /// once the predicate stops holding.
+    ///
+    /// The metric need not exist yet: watching a name before its first sample
+    /// queues the trip and binds it when the metric first registers, so test
+    /// harnesses can install watches up front.
+    pub fn watch<F>(&self, name: &str, predicate: F) -> Receiver<WatchEvent>
+    where
+        F: Fn(f64) -> bool + Send + 'static,
+    {
+        let (events_tx, events_rx) = bounded(WATCH_EVENT_CAPACITY);
+        let (reply_tx, reply_rx) = bounded(1);
+        let _ = self.cmd_tx.send(Command::Query {
+            selector: Selector::Watch {
+                name: name.into(),
+                predicate: Box::new(predicate),
+                events: events_tx,
+            },
+            reply: reply_tx,
+        });
+        // Wait for the worker to acknowledge installation so the caller does
+        // not race registration of the metric it wants to watch.
+        let _ = reply_rx.recv();
+        events_rx
+    }
 }
 
+/// Bounded backlog of pending [`WatchEvent`]s before trips start dropping.
+const WATCH_EVENT_CAPACITY: usize = 64;
+
 impl Drop for TelemetryRecorder {
     fn drop(&mut self) {
         let _ = self.cmd_tx.send(Command::Exit);
@@ -120,6 +295,9 @@ struct Worker {
     writer: Writer,
     cmd_rx: Receiver<Command>,
     metrics: Vec<Metric>,
+    watches: Vec<Watch>,
+    pending_watches: Vec<PendingWatch>,
+    current_time: u64,
 }
 
 impl Worker {
@@ -146,42 +324,171 @@ impl Worker {
             writer,
             cmd_rx,
             metrics: vec![],
+            watches: vec![],
+            pending_watches: vec![],
+            current_time: 0,
         })
     }
 
     fn run(&mut self) -> Result<(), TelemetryError> {
-        use fstapi::var_dir;
         while let Ok(msg) = self.cmd_rx.recv() {
             match msg {
                 Command::Register {
                     name,
                     kind,
-                    unit: _unit,
+                    unit,
+                    labels,
+                    aggregation,
                     reply,
                 } => {
                     let id = self.metrics.len();
+                    let unit = Unit::parse(&unit);
 
-                    let var = self.writer.create_var(
-                        Self::metric_type(kind),
-                        var_dir::OUTPUT,
-                        Self::metric_size(kind),
-                        &name,
-                        None,
-                    )?;
+                    // Group related channels under nested scopes: one level per
+                    // label, then one per dotted segment of the name, leaving
+                    // the final segment as the variable leaf in the viewer.
+                    let segments: Vec<&str> = name.split('.').collect();
+                    let (leaf, parents) = segments
+                        .split_last()
+                        .expect("split always yields at least one segment");
+                    let mut depth = 0usize;
+                    for (key, value) in &labels {
+                        self.writer.set_scope(
+                            fstapi::scope_type::VCD_SCOPE,
+                            &format!("{key}={value}"),
+                            None,
+                        )?;
+                        depth += 1;
+                    }
+                    for parent in parents {
+                        self.writer
+                            .set_scope(fstapi::scope_type::VCD_SCOPE, parent, None)?;
+                        depth += 1;
+                    }
+
+                    // Wrap the channel(s) in a comment attribute so the canonical
+                    // unit string rides along into the recording and downstream
+                    // viewers can display it next to the channel. The attribute
+                    // binds to the next created *variable*, so for a histogram we
+                    // open the leaf scope first (outside the wrapper) and let the
+                    // comment land on its `count` channel, keeping attachment on a
+                    // variable for every kind. Unit-less metrics skip the wrapper
+                    // entirely rather than emit an empty COMMENT.
+                    let canonical = unit.canonical();
+                    let wrap_unit = !canonical.is_empty();
+                    let is_histogram = matches!(aggregation, Some(Aggregation::Histogram));
+                    if is_histogram {
+                        self.writer
+                            .set_scope(fstapi::scope_type::VCD_SCOPE, leaf, None)?;
+                    }
+                    if wrap_unit {
+                        self.writer.set_attr_begin(
+                            fstapi::attr_type::MISC,
+                            fstapi::misc_type::COMMENT,
+                            &canonical,
+                            0,
+                        )?;
+                    }
+                    let (var, agg) = match aggregation {
+                        // A histogram fans out into a subtree of summary channels
+                        // nested under the metric's own scope (opened above).
+                        Some(Aggregation::Histogram) => {
+                            let hist = self.build_histogram()?;
+                            (hist.count_var, Aggregator::Histogram(hist))
+                        }
+                        Some(Aggregation::Counter) => {
+                            (self.create_channel(leaf, kind)?, Aggregator::Counter { sum: 0 })
+                        }
+                        Some(Aggregation::Gauge) => {
+                            (self.create_channel(leaf, kind)?, Aggregator::Gauge { last: 0.0 })
+                        }
+                        None => (self.create_channel(leaf, kind)?, Aggregator::None),
+                    };
+                    if wrap_unit {
+                        self.writer.set_attr_end()?;
+                    }
+                    if is_histogram {
+                        self.writer.set_upscope()?;
+                    }
 
-                    self.metrics.push(Metric { var, kind });
+                    for _ in 0..depth {
+                        self.writer.set_upscope()?;
+                    }
+
+                    self.metrics.push(Metric {
+                        name,
+                        var,
+                        kind,
+                        unit,
+                        agg,
+                        last_value: None,
+                    });
+                    // Bind any watches that were requested before this metric
+                    // existed. The writer is back at the root scope here, so the
+                    // trip markers land alongside the on-demand ones.
+                    let metric_name = self.metrics[id].name.clone();
+                    let mut i = 0;
+                    while i < self.pending_watches.len() {
+                        if self.pending_watches[i].name == metric_name {
+                            let PendingWatch {
+                                name,
+                                predicate,
+                                events,
+                            } = self.pending_watches.remove(i);
+                            self.install_watch(id, &name, predicate, events)?;
+                        } else {
+                            i += 1;
+                        }
+                    }
                     let _ = reply.send(id);
                 }
                 Command::Timestamp(ts) => {
+                    self.current_time = ts;
                     self.writer.emit_time_change(ts)?;
+                    self.flush_aggregates()?;
                 }
                 Command::Update { metric, value } => {
-                    let Some(metric) = self.metrics.get(metric) else {
+                    let Some(m) = self.metrics.get(metric) else {
                         continue;
                     };
-                    let (value, len) = Self::metric_value(metric.kind, value);
-                    // println!("{metric:?} => {value:?}");
-                    self.writer.emit_value_change(metric.var, &value[..len])?;
+                    let (kind, var) = (m.kind, m.var);
+                    let (buf, len) = Self::metric_value(kind, value.clone());
+                    self.writer.emit_value_change(var, &buf[..len])?;
+                    self.observe_value(metric, Self::as_f64(&value))?;
+                }
+                Command::Increment { metric, delta } => {
+                    let mut updated = None;
+                    if let Some(m) = self.metrics.get_mut(metric) {
+                        if let Aggregator::Counter { sum } = &mut m.agg {
+                            *sum += delta;
+                            updated = Some(*sum as f64);
+                        }
+                    }
+                    if let Some(value) = updated {
+                        self.observe_value(metric, value)?;
+                    }
+                }
+                Command::Observe { metric, value } => {
+                    let mut touched = false;
+                    if let Some(m) = self.metrics.get_mut(metric) {
+                        match &mut m.agg {
+                            Aggregator::Gauge { last } => {
+                                *last = value;
+                                touched = true;
+                            }
+                            Aggregator::Histogram(hist) => {
+                                hist.observe(value);
+                                touched = true;
+                            }
+                            _ => {}
+                        }
+                    }
+                    if touched {
+                        self.observe_value(metric, value)?;
+                    }
+                }
+                Command::Query { selector, reply } => {
+                    self.handle_query(selector, reply)?;
                 }
                 Command::Exit => break,
             }
@@ -209,29 +516,467 @@ impl Worker {
 
     fn metric_size(kind: MetricKind) -> u32 {
         match kind {
-            MetricKind::Int8 => 1,
-            MetricKind::Int16 => 8,
-            MetricKind::Int32 => 8,
-            MetricKind::Int64 => 8,
-            MetricKind::Uint8 => 1,
-            MetricKind::Uint16 => 8,
-            MetricKind::Uint32 => 8,
-            MetricKind::Uint64 => 8,
-            MetricKind::Float32 => 8,
-            MetricKind::Float64 => 8,
+            MetricKind::Int8 | MetricKind::Uint8 => 8,
+            MetricKind::Int16 | MetricKind::Uint16 => 16,
+            MetricKind::Int32 | MetricKind::Uint32 => 32,
+            MetricKind::Int64 | MetricKind::Uint64 => 64,
+            // Reals are emitted as the raw 8-byte double, not a bit string.
+            MetricKind::Float32 | MetricKind::Float64 => 8,
         }
     }
 
-    fn metric_value(kind: MetricKind, value: Value) -> ([u8; 8], usize) {
+    fn metric_value(kind: MetricKind, value: Value) -> ([u8; 64], usize) {
+        let mut buf = [0u8; 64];
         match (kind, value) {
-            (MetricKind::Float64 | MetricKind::Float32, Value::Float64(v)) => (v.to_ne_bytes(), 8),
-            _ => ([0u8; 8], 8),
+            (MetricKind::Float64 | MetricKind::Float32, value) => {
+                // Integer values (e.g. a counter's running total) are converted
+                // numerically onto a real channel.
+                buf[..8].copy_from_slice(&Self::as_f64(&value).to_ne_bytes());
+                (buf, 8)
+            }
+            (kind, value) => {
+                // Integer channels want a `metric_size`-wide ASCII bit string,
+                // most-significant bit first. Casting through `u64` keeps the
+                // two's-complement representation, so shifting out the low
+                // `width` bits naturally sign-extends signed values. A real
+                // value (e.g. a gauge's last observation) is truncated to an
+                // integer first.
+                let width = Self::metric_size(kind) as usize;
+                let bits = match value {
+                    Value::Int64(v) => v as u64,
+                    Value::Uint64(v) => v,
+                    Value::Float64(v) => v as i64 as u64,
+                };
+                for (i, slot) in buf[..width].iter_mut().enumerate() {
+                    *slot = b'0' + ((bits >> (width - 1 - i)) & 1) as u8;
+                }
+                (buf, width)
+            }
         }
     }
+
+    /// Create a plain variable channel in the current scope.
+    fn create_channel(&mut self, name: &str, kind: MetricKind) -> Result<Handle, TelemetryError> {
+        Ok(self.writer.create_var(
+            Self::metric_type(kind),
+            fstapi::var_dir::OUTPUT,
+            Self::metric_size(kind),
+            name,
+            None,
+        )?)
+    }
+
+    /// Allocate the channel fan-out that backs a histogram metric: one counter
+    /// per `(lo, hi]` bucket plus `count`/`sum`/`min`/`max` and a channel per
+    /// tracked quantile. The per-bucket counts are non-cumulative, so the
+    /// channels are named `bucket_<lo>_<hi>` rather than the cumulative `le_`
+    /// convention.
+    fn build_histogram(&mut self) -> Result<Histogram, TelemetryError> {
+        let boundaries: &'static [f64] = &HISTOGRAM_BOUNDS;
+        // Create `count` first so the unit comment opened by the caller binds to
+        // it rather than to the first bucket channel.
+        let count_var = self.create_channel("count", MetricKind::Uint64)?;
+        let mut bucket_vars = Vec::with_capacity(boundaries.len() + 1);
+        let mut lo = 0.0;
+        for &hi in boundaries {
+            bucket_vars.push(self.create_channel(&bucket_name(lo, hi), MetricKind::Uint64)?);
+            lo = hi;
+        }
+        bucket_vars.push(self.create_channel(&format!("bucket_{lo}_inf"), MetricKind::Uint64)?);
+        let sum_var = self.create_channel("sum", MetricKind::Float64)?;
+        let min_var = self.create_channel("min", MetricKind::Float64)?;
+        let max_var = self.create_channel("max", MetricKind::Float64)?;
+        let mut quantile_vars = Vec::with_capacity(HISTOGRAM_QUANTILES.len());
+        for &q in &HISTOGRAM_QUANTILES {
+            let pct = (q * 100.0) as u32;
+            quantile_vars.push((q, self.create_channel(&format!("p{pct}"), MetricKind::Float64)?));
+        }
+        Ok(Histogram {
+            boundaries,
+            counts: vec![0; boundaries.len() + 1],
+            bucket_vars,
+            min: 0.0,
+            max: 0.0,
+            sum: 0.0,
+            count: 0,
+            count_var,
+            sum_var,
+            min_var,
+            max_var,
+            quantile_vars,
+        })
+    }
+
+    /// Emit the current value of every aggregating metric at the active time.
+    fn flush_aggregates(&mut self) -> Result<(), TelemetryError> {
+        for metric in &self.metrics {
+            match &metric.agg {
+                Aggregator::None => {}
+                Aggregator::Counter { sum } => {
+                    Self::emit(&mut self.writer, metric.var, metric.kind, Value::Int64(*sum))?;
+                }
+                Aggregator::Gauge { last } => {
+                    Self::emit(&mut self.writer, metric.var, metric.kind, Value::Float64(*last))?;
+                }
+                Aggregator::Histogram(hist) => {
+                    for (var, count) in hist.bucket_vars.iter().zip(hist.counts.iter()) {
+                        Self::emit(&mut self.writer, *var, MetricKind::Uint64, Value::Uint64(*count))?;
+                    }
+                    Self::emit(
+                        &mut self.writer,
+                        hist.count_var,
+                        MetricKind::Uint64,
+                        Value::Uint64(hist.count),
+                    )?;
+                    for (var, value) in [
+                        (hist.sum_var, hist.sum),
+                        (hist.min_var, hist.min),
+                        (hist.max_var, hist.max),
+                    ] {
+                        Self::emit(&mut self.writer, var, MetricKind::Float64, Value::Float64(value))?;
+                    }
+                    for (q, var) in &hist.quantile_vars {
+                        Self::emit(
+                            &mut self.writer,
+                            *var,
+                            MetricKind::Float64,
+                            Value::Float64(hist.quantile(*q)),
+                        )?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn emit(
+        writer: &mut Writer,
+        var: Handle,
+        kind: MetricKind,
+        value: Value,
+    ) -> Result<(), TelemetryError> {
+        let (buf, len) = Self::metric_value(kind, value);
+        writer.emit_value_change(var, &buf[..len])?;
+        Ok(())
+    }
+
+    fn as_f64(value: &Value) -> f64 {
+        match value {
+            Value::Int64(v) => *v as f64,
+            Value::Uint64(v) => *v as f64,
+            Value::Float64(v) => *v,
+        }
+    }
+
+    /// Update the live-value cache for a metric and evaluate any watches set on
+    /// it against the new value.
+    fn observe_value(&mut self, id: MetricId, value: f64) -> Result<(), TelemetryError> {
+        if let Some(metric) = self.metrics.get_mut(id) {
+            metric.last_value = Some(value);
+        }
+        for watch in &mut self.watches {
+            if watch.metric != id {
+                continue;
+            }
+            let tripped = (watch.predicate)(value);
+            if tripped && watch.armed {
+                watch.armed = false;
+                Self::emit(&mut self.writer, watch.marker, MetricKind::Uint8, Value::Uint64(1))?;
+                let event = WatchEvent {
+                    name: self.metrics[id].name.clone(),
+                    value,
+                    timestamp: self.current_time,
+                };
+                let _ = watch.events.try_send(event);
+            } else if !tripped && !watch.armed {
+                watch.armed = true;
+                Self::emit(&mut self.writer, watch.marker, MetricKind::Uint8, Value::Uint64(0))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_query(&mut self, selector: Selector, reply: Sender<Reply>) -> Result<(), TelemetryError> {
+        match selector {
+            Selector::List => {
+                let list = self
+                    .metrics
+                    .iter()
+                    .map(|m| MetricSnapshot {
+                        name: m.name.clone(),
+                        kind: m.kind,
+                        unit: m.unit.canonical(),
+                        value: m.last_value,
+                    })
+                    .collect();
+                let _ = reply.send(Reply::List(list));
+            }
+            Selector::Value(name) => {
+                let value = self
+                    .metrics
+                    .iter()
+                    .find(|m| m.name == name)
+                    .and_then(|m| m.last_value);
+                let _ = reply.send(Reply::Value(value));
+            }
+            Selector::Watch {
+                name,
+                predicate,
+                events,
+            } => {
+                match self.metrics.iter().position(|m| m.name == name) {
+                    // The metric already exists: arm the trip immediately.
+                    Some(metric) => self.install_watch(metric, &name, predicate, events)?,
+                    // Not registered yet: queue it so the first `metric!` hit
+                    // binds it, leaving the caller with a live channel.
+                    None => self.pending_watches.push(PendingWatch {
+                        name,
+                        predicate,
+                        events,
+                    }),
+                }
+                let _ = reply.send(Reply::Watch(true));
+            }
+        }
+        Ok(())
+    }
+
+    /// Create the trip marker channel for a metric and arm a [`Watch`] on it.
+    fn install_watch(
+        &mut self,
+        metric: MetricId,
+        name: &str,
+        predicate: Predicate,
+        events: Sender<WatchEvent>,
+    ) -> Result<(), TelemetryError> {
+        let marker = self.create_channel(&format!("{name}.trip"), MetricKind::Uint8)?;
+        self.watches.push(Watch {
+            metric,
+            predicate,
+            marker,
+            armed: true,
+            events,
+        });
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
 struct Metric {
+    name: String,
     var: Handle,
     kind: MetricKind,
+    unit: Unit,
+    agg: Aggregator,
+    /// Latest recorded value, cached so the query channel can report it
+    /// without reading back the FST file.
+    last_value: Option<f64>,
+}
+
+/// Name the non-cumulative channel for the `(lo, hi]` bucket.
+fn bucket_name(lo: f64, hi: f64) -> String {
+    format!("bucket_{lo}_{hi}")
+}
+
+/// Default histogram bucket boundaries: powers of two up to 2^15. Each
+/// observation lands in exactly one `(lo, hi]` bucket — the first whose upper
+/// bound it does not exceed — with a final `bucket_<max>_inf` overflow bucket
+/// for the rest. The per-bucket counts are non-cumulative.
+const HISTOGRAM_BOUNDS: [f64; 16] = [
+    1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0, 128.0, 256.0, 512.0, 1024.0, 2048.0, 4096.0, 8192.0,
+    16384.0, 32768.0,
+];
+
+/// Quantiles estimated from the histogram buckets on each flush.
+const HISTOGRAM_QUANTILES: [f64; 3] = [0.5, 0.9, 0.99];
+
+/// Per-metric client-side accumulator state.
+#[derive(Debug)]
+enum Aggregator {
+    /// Raw per-sample channel; values are emitted as they arrive.
+    None,
+    /// Monotonic running total.
+    Counter { sum: i64 },
+    /// Last observed value.
+    Gauge { last: f64 },
+    /// Bucketed distribution with summary channels.
+    Histogram(Histogram),
+}
+
+/// A non-cumulative `(lo, hi]`-bucket histogram and the channels its summaries
+/// flush into.
+#[derive(Debug)]
+struct Histogram {
+    boundaries: &'static [f64],
+    counts: Vec<u64>,
+    bucket_vars: Vec<Handle>,
+    min: f64,
+    max: f64,
+    sum: f64,
+    count: u64,
+    count_var: Handle,
+    sum_var: Handle,
+    min_var: Handle,
+    max_var: Handle,
+    quantile_vars: Vec<(f64, Handle)>,
+}
+
+impl Histogram {
+    /// Fold a single observation into the running buckets and summaries.
+    fn observe(&mut self, value: f64) {
+        let idx = self
+            .boundaries
+            .iter()
+            .position(|&bound| value <= bound)
+            .unwrap_or(self.boundaries.len());
+        self.counts[idx] += 1;
+        if self.count == 0 || value < self.min {
+            self.min = value;
+        }
+        if self.count == 0 || value > self.max {
+            self.max = value;
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    /// Estimate the given quantile from the cumulative bucket counts, returning
+    /// the upper bound of the bucket the quantile falls into.
+    fn quantile(&self, q: f64) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        let target = q * self.count as f64;
+        let mut cumulative = 0u64;
+        for (idx, &count) in self.counts.iter().enumerate() {
+            cumulative += count;
+            if cumulative as f64 >= target {
+                return self.boundaries.get(idx).copied().unwrap_or(self.max);
+            }
+        }
+        self.max
+    }
+}
+
+/// A parsed measurement unit: a base quantity together with the scaling
+/// prefix that was stripped off it.
+///
+/// The metrics ecosystem eventually learned to keep decimal (SI) and binary
+/// (IEC) prefixes apart — `k`/`M`/`G` step by 1000 while `Ki`/`Mi`/`Gi` step
+/// by 1024 — so `bandwidth [By/s]` and `mem [KiBy]` round-trip with the right
+/// meaning instead of being collapsed onto a single "kilo".
+#[derive(Debug, Clone, PartialEq)]
+struct Unit {
+    /// Base quantity with the prefix removed, e.g. `s`, `By`, `1/s`, `%`.
+    base: String,
+    /// Scaling prefix applied to the base quantity.
+    scale: Scale,
+}
+
+/// The scaling prefix in front of a [`Unit`]'s base quantity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scale {
+    /// No prefix; the value is already expressed in the base quantity.
+    None,
+    /// SI decimal prefix carrying a power of ten (`k` = `3`, `m` = `-3`).
+    Decimal(i32),
+    /// IEC binary prefix carrying a power of 1024 (`Ki` = `1`, `Mi` = `2`).
+    Binary(i32),
+}
+
+impl Unit {
+    /// Parse a unit string such as `"ns"`, `"By"`, `"KiBy"` or `"1/s"`.
+    ///
+    /// Prefix detection is conservative: a prefix is only split off when a
+    /// non-empty base quantity remains, so bare `"m"` stays the base unit
+    /// rather than being read as milli-nothing.
+    fn parse(unit: &str) -> Self {
+        if let Some((exp, base)) = Self::binary_prefix(unit) {
+            if !base.is_empty() {
+                return Self {
+                    base: base.into(),
+                    scale: Scale::Binary(exp),
+                };
+            }
+        }
+        if let Some((exp, base)) = Self::decimal_prefix(unit) {
+            if !base.is_empty() {
+                return Self {
+                    base: base.into(),
+                    scale: Scale::Decimal(exp),
+                };
+            }
+        }
+        Self {
+            base: unit.into(),
+            scale: Scale::None,
+        }
+    }
+
+    fn binary_prefix(unit: &str) -> Option<(i32, &str)> {
+        let mut chars = unit.chars();
+        let prefix = chars.next()?;
+        if chars.next()? != 'i' {
+            return None;
+        }
+        let exp = match prefix {
+            'K' => 1,
+            'M' => 2,
+            'G' => 3,
+            'T' => 4,
+            'P' => 5,
+            _ => return None,
+        };
+        Some((exp, &unit[2..]))
+    }
+
+    fn decimal_prefix(unit: &str) -> Option<(i32, &str)> {
+        let prefix = unit.chars().next()?;
+        let exp = match prefix {
+            'k' => 3,
+            'M' => 6,
+            'G' => 9,
+            'T' => 12,
+            'm' => -3,
+            'u' => -6,
+            'n' => -9,
+            'p' => -12,
+            _ => return None,
+        };
+        Some((exp, &unit[prefix.len_utf8()..]))
+    }
+
+    /// Reconstruct the canonical `<prefix><base>` string for this unit.
+    fn canonical(&self) -> String {
+        match self.scale {
+            Scale::None => self.base.clone(),
+            Scale::Decimal(exp) => format!("{}{}", Self::decimal_symbol(exp), self.base),
+            Scale::Binary(exp) => format!("{}i{}", Self::binary_symbol(exp), self.base),
+        }
+    }
+
+    fn decimal_symbol(exp: i32) -> &'static str {
+        match exp {
+            3 => "k",
+            6 => "M",
+            9 => "G",
+            12 => "T",
+            -3 => "m",
+            -6 => "u",
+            -9 => "n",
+            -12 => "p",
+            _ => "",
+        }
+    }
+
+    fn binary_symbol(exp: i32) -> &'static str {
+        match exp {
+            1 => "K",
+            2 => "M",
+            3 => "G",
+            4 => "T",
+            5 => "P",
+            _ => "",
+        }
+    }
 }
@@ -14,6 +14,7 @@ macro_rules! metric {
                     stringify!($name),
                     $crate::recorder::__metric_kind!($type),
                     $unit,
+                    &[],
                 )
             });
             $crate::paste! {
@@ -21,12 +22,34 @@ macro_rules! metric {
             }
         }
     }};
+    ($name:ident [$unit:literal] { $($key:ident = $val:expr),* $(,)? } : $type:ident = $value:expr) => {{
+        // Labelled call sites can vary their values at runtime, so the id is
+        // keyed by the resolved label set rather than memoized once.
+        static IDS: std::sync::OnceLock<std::sync::Mutex<$crate::recorder::IdCache>> =
+            std::sync::OnceLock::new();
+        if let Some(recorder) = $crate::recorder::RECORDER.get() {
+            $(let $key = ($val).to_string();)*
+            let labels: &[(&str, &str)] = &[$((stringify!($key), $key.as_str())),*];
+            let id = $crate::recorder::__resolve_id(
+                &IDS,
+                recorder,
+                stringify!($name),
+                $crate::recorder::__metric_kind!($type),
+                $unit,
+                labels,
+            );
+            $crate::paste! {
+                recorder.[<record_ $type>](id, $value);
+            }
+        }
+    }};
 }
 
 #[cfg(not(feature = "enable"))]
 #[macro_export]
 macro_rules! metric {
-    ($name:ident [$unit:literal] : $type:ty = $value:expr, [unit:lit]) => {};
+    ($name:ident [$unit:literal] : $type:ident = $value:expr) => {};
+    ($name:ident [$unit:literal] { $($key:ident = $val:expr),* $(,)? } : $type:ident = $value:expr) => {};
 }
 
 #[cfg(feature = "enable")]
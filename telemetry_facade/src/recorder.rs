@@ -2,9 +2,11 @@ use core::{
     cell::UnsafeCell,
     sync::atomic::{AtomicUsize, Ordering},
 };
+use std::sync::{Mutex, OnceLock};
 
 pub trait Recorder: Send + Sync {
-    fn allocate(&self, name: &str, kind: MetricKind, unit: &str) -> MetricId;
+    fn allocate(&self, name: &str, kind: MetricKind, unit: &str, labels: &[(&str, &str)])
+        -> MetricId;
 
     fn record_i8(&self, id: usize, value: u8) {
         self.record_i64(id, value.into());
@@ -30,6 +32,37 @@ pub trait Recorder: Send + Sync {
         self.record_f64(id, value.into());
     }
     fn record_f64(&self, id: usize, value: f64);
+
+    /// Allocate a metric that the backend aggregates client-side rather than
+    /// recording one value change per sample.
+    ///
+    /// `value_kind` is the scalar type of the underlying observations (e.g.
+    /// [`MetricKind::Uint64`] for a counter). Backends that do not aggregate
+    /// fall back to a plain channel.
+    fn allocate_aggregate(
+        &self,
+        name: &str,
+        aggregation: Aggregation,
+        value_kind: MetricKind,
+        unit: &str,
+        labels: &[(&str, &str)],
+    ) -> MetricId {
+        let _ = aggregation;
+        self.allocate(name, value_kind, unit, labels)
+    }
+
+    /// Add `delta` to a [`Aggregation::Counter`] metric. No-op on backends
+    /// without aggregation support.
+    fn increment(&self, id: MetricId, delta: i64) {
+        let _ = (id, delta);
+    }
+
+    /// Record an observation against a [`Aggregation::Gauge`] or
+    /// [`Aggregation::Histogram`] metric. No-op on backends without
+    /// aggregation support.
+    fn observe(&self, id: MetricId, value: f64) {
+        let _ = (id, value);
+    }
 }
 
 pub struct RecorderCell {
@@ -82,6 +115,47 @@ pub static RECORDER: RecorderCell = RecorderCell::new();
 
 pub type MetricId = usize;
 
+/// Per-call-site cache mapping a resolved label set to the id it allocated.
+///
+/// A `metric!` line that fires with different runtime label values needs a
+/// distinct channel per combination, so a single `OnceLock<usize>` no longer
+/// suffices; this small association list is keyed by the owned labels.
+pub type IdCache = Vec<(Box<[(Box<str>, Box<str>)]>, MetricId)>;
+
+/// Resolve the metric id for a call site with the given label set, allocating
+/// a new channel the first time a combination is seen.
+///
+/// Used by the `metric!` macro; the `#[doc(hidden)]` name is not part of the
+/// public surface.
+#[doc(hidden)]
+pub fn __resolve_id(
+    cache: &OnceLock<Mutex<IdCache>>,
+    recorder: &dyn Recorder,
+    name: &str,
+    kind: MetricKind,
+    unit: &str,
+    labels: &[(&str, &str)],
+) -> MetricId {
+    let mut cache = cache.get_or_init(|| Mutex::new(Vec::new())).lock().unwrap();
+    for (key, id) in cache.iter() {
+        if key.len() == labels.len()
+            && key
+                .iter()
+                .zip(labels)
+                .all(|((k, v), (lk, lv))| k.as_ref() == *lk && v.as_ref() == *lv)
+        {
+            return *id;
+        }
+    }
+    let id = recorder.allocate(name, kind, unit, labels);
+    let key = labels
+        .iter()
+        .map(|(k, v)| ((*k).into(), (*v).into()))
+        .collect();
+    cache.push((key, id));
+    id
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum MetricKind {
     Int8,
@@ -96,6 +170,18 @@ pub enum MetricKind {
     Float64,
 }
 
+/// How a metric is aggregated client-side before it reaches the recording.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Aggregation {
+    /// Monotonically summed delta, flushed as a running total per timestep.
+    Counter,
+    /// Last-write-wins value, flushed as-is per timestep.
+    Gauge,
+    /// Bucketed distribution, flushed as per-bucket counts plus quantile
+    /// estimates and min/max/count/sum summaries per timestep.
+    Histogram,
+}
+
 #[macro_export]
 macro_rules! __metric_kind {
     (Int8) => {